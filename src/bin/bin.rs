@@ -1,5 +1,5 @@
 use anyhow::{bail, ensure, Context, Result};
-use cargo_bin::manifest::Manifest;
+use cargo_bin::manifest::{Manifest, TargetFields, TargetKind};
 use cargo_bin::project;
 use std::fs;
 use std::io::Write;
@@ -15,6 +15,10 @@ enum Command {
         #[structopt()]
         path: String,
 
+        /// kind of target to create
+        #[structopt(short = "k", long, default_value = "bin")]
+        kind: TargetKind,
+
         /// assume all answers are yes
         #[structopt(short = "y")]
         assume_yes: bool,
@@ -22,6 +26,14 @@ enum Command {
         /// force create to override existing binary file
         #[structopt(short = "f", long)]
         force: bool,
+
+        /// required-features to set on the target
+        #[structopt(long)]
+        required_features: Vec<String>,
+
+        /// edition to set on the target
+        #[structopt(long)]
+        edition: Option<String>,
     },
     /// Add missing and remove unused
     Tidy {},
@@ -33,9 +45,21 @@ enum Command {
         #[structopt()]
         path: String,
 
+        /// kind of target being added
+        #[structopt(short = "k", long, default_value = "bin")]
+        kind: TargetKind,
+
         /// force create to override existing binary file
         #[structopt(short = "f", long)]
         force: bool,
+
+        /// required-features to set on the target
+        #[structopt(long)]
+        required_features: Vec<String>,
+
+        /// edition to set on the target
+        #[structopt(long)]
+        edition: Option<String>,
     },
 }
 
@@ -52,6 +76,21 @@ struct Opt {
     /// dry run
     #[structopt(long, global = true)]
     dry_run: bool,
+
+    /// path to Cargo.toml, instead of searching for one from the current directory
+    #[structopt(long, global = true, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+
+    /// workspace member to operate on (repeatable); with none given at a
+    /// workspace root, every member is operated on
+    #[structopt(short = "p", long = "package", global = true)]
+    package: Vec<String>,
+
+    /// with `tidy`, compute the result without writing, exiting non-zero if
+    /// Cargo.toml is out of sync with the source tree (for use in CI); other
+    /// subcommands ignore it
+    #[structopt(long, global = true)]
+    check: bool,
 }
 
 fn main() -> Result<()> {
@@ -65,17 +104,37 @@ fn main() -> Result<()> {
         println!("{:?}", opt);
     }
 
-    let root_path = project::root_path()?;
-    if opt.verbose {
-        println!("root_path: {:?}", root_path);
+    for manifest_path in resolve_manifest_paths(&opt)? {
+        if opt.verbose {
+            println!("manifest_path: {:?}", manifest_path);
+        }
+        run(&opt, manifest_path)?;
     }
 
-    match opt.cmd {
-        Command::Add { path, force } => {
-            let bin_path = get_bin_path(path)?;
-            add_binaries(AddArgs {
-                bin_path,
-                force,
+    Ok(())
+}
+
+fn run(opt: &Opt, manifest_path: PathBuf) -> Result<()> {
+    let root_path = manifest_path
+        .parent()
+        .with_context(|| format!("{:?} has no parent", manifest_path))?
+        .to_path_buf();
+
+    match &opt.cmd {
+        Command::Add {
+            path,
+            kind,
+            force,
+            required_features,
+            edition,
+        } => {
+            let target_path = get_target_path(path.clone(), *kind)?;
+            add_targets(AddArgs {
+                target_path,
+                kind: *kind,
+                force: *force,
+                fields: target_fields(required_features, edition),
+                manifest_path,
                 root_path,
                 dry_run: opt.dry_run,
                 verbose: opt.verbose,
@@ -83,22 +142,30 @@ fn main() -> Result<()> {
         }
         Command::New {
             path,
+            kind,
             assume_yes: _,
             force,
+            required_features,
+            edition,
         } => {
             new_binary(NewBinaryArgs {
+                manifest_path,
                 root_path,
-                path,
-                force,
+                path: path.clone(),
+                kind: *kind,
+                force: *force,
+                fields: target_fields(required_features, edition),
                 dry_run: opt.dry_run,
                 verbose: opt.verbose,
             })?;
         }
         Command::Remove {} => {}
         Command::Tidy {} => {
-            tide_binaries(TideArgs {
+            tidy_targets(TidyArgs {
+                manifest_path,
                 root_path,
                 dry_run: opt.dry_run,
+                check: opt.check,
                 verbose: opt.verbose,
             })?;
         }
@@ -107,19 +174,79 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// resolve which manifest(s) a command should run against: an explicit
+/// `--manifest-path`, or the usual upward search; then, if that manifest is
+/// a workspace root, expand to the selected `-p/--package` members (or all
+/// of them, if none were named).
+fn resolve_manifest_paths(opt: &Opt) -> Result<Vec<PathBuf>> {
+    let root_manifest = match &opt.manifest_path {
+        Some(path) => {
+            fs::canonicalize(path).with_context(|| format!("{:?} not found", path))?
+        }
+        None => project::search_manifest()?,
+    };
+
+    let manifest = Manifest::open(&root_manifest)?;
+    if !manifest.is_workspace() {
+        ensure!(
+            opt.package.is_empty(),
+            "-p/--package given but {:?} is not a workspace",
+            root_manifest
+        );
+        return Ok(vec![root_manifest]);
+    }
+
+    let members = manifest.workspace_members()?;
+    if opt.package.is_empty() {
+        return Ok(members);
+    }
+
+    opt.package
+        .iter()
+        .map(|name| {
+            members
+                .iter()
+                .find(|member| {
+                    Manifest::open(member)
+                        .ok()
+                        .and_then(|m| m.package_name().map(str::to_string))
+                        .as_deref()
+                        == Some(name.as_str())
+                })
+                .cloned()
+                .with_context(|| format!("package {:?} not found in workspace", name))
+        })
+        .collect()
+}
+
+/// build a `TargetFields` from the `--required-features`/`--edition` flags
+fn target_fields(required_features: &[String], edition: &Option<String>) -> TargetFields {
+    TargetFields {
+        required_features: if required_features.is_empty() {
+            None
+        } else {
+            Some(required_features.to_vec())
+        },
+        edition: edition.clone(),
+    }
+}
+
 struct NewBinaryArgs {
     path: String, // binary path
+    kind: TargetKind,
     force: bool,
+    fields: TargetFields,
+    manifest_path: PathBuf,
     root_path: PathBuf,
     dry_run: bool,
     verbose: bool,
 }
 
 fn new_binary(args: NewBinaryArgs) -> Result<()> {
-    let bin_path = get_bin_path(args.path.clone())?;
-    if bin_path.exists() {
+    let target_path = get_target_path(args.path.clone(), args.kind)?;
+    if target_path.exists() {
         ensure!(
-            bin_path.is_file(),
+            target_path.is_file(),
             "{:?} already exits and is not a file",
             args.path
         );
@@ -128,26 +255,33 @@ fn new_binary(args: NewBinaryArgs) -> Result<()> {
         }
     }
 
-    println!("create {:?}", bin_path);
+    println!("create {:?}", target_path);
     if !args.dry_run {
+        if let Some(parent) = target_path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("create dir {:?} err", parent))?;
+        }
         let mut file = fs::OpenOptions::new()
             .write(true)
             .create(true)
             .truncate(true)
-            .open(bin_path.clone())
-            .with_context(|| format!("open file {:?} err", bin_path))?;
+            .open(target_path.clone())
+            .with_context(|| format!("open file {:?} err", target_path))?;
         let content = r#"
 fn main() {
   println!("hello world");
 }"#;
         file.write_all(content.as_bytes())
-            .with_context(|| format!("write to {:?} err", bin_path))?;
+            .with_context(|| format!("write to {:?} err", target_path))?;
     }
 
     // TODO only add the new one
-    add_binaries(AddArgs {
-        bin_path,
+    add_targets(AddArgs {
+        target_path,
+        kind: args.kind,
         force: args.force,
+        fields: args.fields,
+        manifest_path: args.manifest_path,
         root_path: args.root_path,
         dry_run: args.dry_run,
         verbose: args.verbose,
@@ -157,7 +291,10 @@ fn main() {
 }
 
 pub struct AddArgs {
-    bin_path: PathBuf,
+    target_path: PathBuf,
+    kind: TargetKind,
+    fields: TargetFields,
+    manifest_path: PathBuf,
     root_path: PathBuf,
     force: bool,
     dry_run: bool,
@@ -165,15 +302,17 @@ pub struct AddArgs {
 }
 
 /// won't check if path is valid
-fn add_binaries(args: AddArgs) -> Result<()> {
-    let mut manifest = Manifest::new()?;
+fn add_targets(args: AddArgs) -> Result<()> {
+    let mut manifest = Manifest::open(&args.manifest_path)?;
 
-    let BinInfo { name, path } = get_bin_info(
-        &args.bin_path.to_str().unwrap(),
+    let TargetInfo { name, path, kind } = get_target_info(
+        args.kind,
+        &args.target_path.to_str().unwrap(),
         args.root_path.to_str().unwrap(),
     )?;
+    let name = name_or_package_fallback(name, &manifest)?;
 
-    if manifest.exists(&name, &path) {
+    if manifest.exists(kind, &name, &path) {
         if !args.force {
             println!(
                 "same name {:?} or path {:?} already exists, use --force to override",
@@ -190,8 +329,8 @@ fn add_binaries(args: AddArgs) -> Result<()> {
         }
     }
 
-    println!("add bin: {:?} -> {:?}", name, path);
-    manifest.add_bin(&name, &path)?;
+    println!("add {}: {:?} -> {:?}", kind.key(), name, path);
+    manifest.add_target(kind, &name, &path, &args.fields)?;
 
     if !args.dry_run {
         manifest.write()?;
@@ -200,55 +339,90 @@ fn add_binaries(args: AddArgs) -> Result<()> {
     Ok(())
 }
 
-struct TideArgs {
+struct TidyArgs {
+    manifest_path: PathBuf,
     root_path: PathBuf,
     dry_run: bool,
+    check: bool,
     verbose: bool,
 }
 
-fn tide_binaries(args: TideArgs) -> Result<()> {
-    let mut manifest = Manifest::new()?;
-
-    // check existing bins
-    let mut to_remove = vec![];
-    manifest.foreach_bin(|name, path| {
-        let name = name.unwrap_or_default().to_string();
-        let path = path.unwrap_or_default().to_string();
-
-        if name.is_empty() || path.is_empty() {
-            println!("invalid bin, empty name: {} or path: {}", name, path);
-            return;
-        }
+fn tidy_targets(args: TidyArgs) -> Result<()> {
+    let mut manifest = Manifest::open(&args.manifest_path)?;
+    let mut out_of_sync = false;
+
+    // check existing targets of every kind
+    for kind in TargetKind::all().iter() {
+        let kind = *kind;
+        let mut to_remove = vec![];
+        manifest.foreach_target(kind, |name, path| {
+            let name = name.unwrap_or_default().to_string();
+            let path = path.unwrap_or_default().to_string();
+
+            if name.is_empty() || path.is_empty() {
+                println!("invalid {}, empty name: {} or path: {}", kind.key(), name, path);
+                return;
+            }
 
-        // path not exists should be removed
-        if !Path::new(&path).exists() {
-            to_remove.push((name, path));
+            // path not exists should be removed; resolve relative to the manifest's
+            // root so `--check` gives the same answer regardless of the process cwd
+            if !args.root_path.join(&path).exists() {
+                to_remove.push((name, path));
+            }
+        });
+        for (name, path) in to_remove {
+            println!("{}remove {} -> {}", check_prefix(args.check), name, path);
+            manifest.remove_target(kind, &name, &path);
+            out_of_sync = true;
         }
-    });
-    for (name, path) in to_remove {
-        println!("remove {} -> {}", name, path);
-        manifest.remove_bin(&name, &path);
     }
 
     // add the new main files
     let main_files = project::find_main_file(&args.root_path)?;
     for entry in main_files.iter() {
         // canonicalize will check if file exists
-        let bin_path = fs::canonicalize(entry)
-            .with_context(|| format!("{:?} convert to absolute path err", entry))?;
+        let target_path = fs::canonicalize(&entry.path)
+            .with_context(|| format!("{:?} convert to absolute path err", entry.path))?;
 
-        let BinInfo { name, path } =
-            get_bin_info(bin_path.to_str().unwrap(), args.root_path.to_str().unwrap())?;
+        let TargetInfo { name, path, kind } = get_target_info(
+            entry.kind,
+            target_path.to_str().unwrap(),
+            args.root_path.to_str().unwrap(),
+        )?;
+        let name = name_or_package_fallback(name, &manifest)?;
 
-        if manifest.exists(&name, &path) {
+        if manifest.exists(kind, &name, &path) {
             if args.verbose {
-                println!("bin {}: {} already exists, skip", name, path)
+                println!("{} {}: {} already exists, skip", kind.key(), name, path)
             }
             continue;
         }
 
-        println!("add new bin: name: {:?}, path: {:?},", name, path);
-        manifest.add_bin(&name, &path)?;
+        println!(
+            "{}add new {}: name: {:?}, path: {:?},",
+            check_prefix(args.check),
+            kind.key(),
+            name,
+            path
+        );
+        manifest.add_target(kind, &name, &path, &TargetFields::default())?;
+        out_of_sync = true;
+    }
+
+    for kind in TargetKind::all().iter() {
+        if !manifest.is_sorted(*kind) {
+            println!("{}{} targets are out of order", check_prefix(args.check), kind.key());
+            manifest.sort_targets(*kind);
+            out_of_sync = true;
+        }
+    }
+
+    if args.check {
+        if out_of_sync {
+            bail!("Cargo.toml targets are out of sync with the source tree, run `cargo bin tidy` to fix");
+        }
+        println!("Cargo.toml targets are in sync");
+        return Ok(());
     }
 
     // write the changes
@@ -259,9 +433,18 @@ fn tide_binaries(args: TideArgs) -> Result<()> {
     Ok(())
 }
 
+/// a `--check`-mode message gets a `would ` prefix since nothing is written
+fn check_prefix(check: bool) -> &'static str {
+    if check {
+        "would "
+    } else {
+        ""
+    }
+}
+
 // utils
 
-fn get_bin_path(path: String) -> Result<PathBuf> {
+fn get_target_path(path: String, kind: TargetKind) -> Result<PathBuf> {
     let mut path = path;
     ensure!(!path.is_empty(), "path cannot be empty");
     if !path.ends_with(".rs") {
@@ -269,32 +452,61 @@ fn get_bin_path(path: String) -> Result<PathBuf> {
     }
 
     let path = Path::new(&path);
+    // a bare name (no path separator) is placed under the conventional
+    // directory for its kind, e.g. `foo` --kind example -> examples/foo.rs
+    if path.components().count() == 1 {
+        return Ok(Path::new(kind.dir()).join(path));
+    }
+
     Ok(path.to_path_buf())
 }
 
-struct BinInfo {
+struct TargetInfo {
     name: String,
     path: String,
+    kind: TargetKind,
+}
+
+/// a derived name can come back empty, e.g. for a bare `src/main.rs` that
+/// sits directly under the package root; fall back to the package name in
+/// that case, the same way `cargo init` would name the binary.
+fn name_or_package_fallback(name: String, manifest: &Manifest) -> Result<String> {
+    if !name.is_empty() {
+        return Ok(name);
+    }
+    manifest.package_name().map(str::to_string).with_context(|| {
+        "derived target name is empty and manifest has no [package] name to fall back to"
+    })
 }
 
 // get name and path without check.
-fn get_bin_info(bin_path: &str, root_path: &str) -> Result<BinInfo> {
+fn get_target_info(kind: TargetKind, target_path: &str, root_path: &str) -> Result<TargetInfo> {
     // path remove root path if possible
-    let path = bin_path
+    let path = target_path
         .trim_start_matches(root_path)
         .trim_start_matches('/');
 
-    // name, remove src if it's under src folder
-    let name = bin_path
-        .trim_start_matches(root_path)
-        .trim_start_matches('/')
-        .trim_start_matches("src")
-        .trim_start_matches('/')
-        .trim_end_matches(".rs")
-        .replace("/", "-");
+    // name: strip the kind's conventional directory (or plain "src" for a
+    // top-level bin like src/main.rs), then the .rs extension
+    let trimmed = path.trim_end_matches(".rs");
+    let rest = match trimmed.strip_prefix(kind.dir()) {
+        Some(rest) => rest.trim_start_matches('/'),
+        None => trimmed.trim_start_matches("src").trim_start_matches('/'),
+    };
+    // `<dir>/main.rs` is cargo's convention for a multi-file target: it takes
+    // the directory's name, not "main". A bare `main` with nothing before it
+    // is the package's own default binary, so it's left empty here and
+    // resolved to the package name by `name_or_package_fallback`.
+    let name = match rest.rsplit_once('/') {
+        Some((parent, "main")) => parent,
+        _ if rest == "main" => "",
+        _ => rest,
+    }
+    .replace('/', "-");
 
-    Ok(BinInfo {
+    Ok(TargetInfo {
         name,
         path: path.to_string(),
+        kind,
     })
 }
@@ -0,0 +1,199 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A single line of a `.gitignore` file, already split into its pieces.
+#[derive(Debug, Clone)]
+struct Pattern {
+    /// the glob, with any leading `!`/`/` and trailing `/` already stripped
+    glob: String,
+    /// `!pattern` re-includes a path an earlier pattern excluded
+    negated: bool,
+    /// `pattern/` only matches directories
+    dir_only: bool,
+    /// `/pattern` only matches at the `.gitignore`'s own directory, not in
+    /// any subdirectory below it
+    anchored: bool,
+}
+
+/// the patterns contributed by a single directory, either a real
+/// `.gitignore` file or the tool's built-in implicit skip list
+struct Layer {
+    dir: PathBuf,
+    patterns: Vec<Pattern>,
+}
+
+/// Tracks `.gitignore` patterns for the directories currently being walked,
+/// nearest (innermost) last. Mirrors git's own precedence: patterns are
+/// evaluated from the outermost directory inward, and the last pattern that
+/// matches wins, so a nested `.gitignore` (or a later line, including a `!`
+/// negation) can override an outer one.
+pub struct IgnoreStack {
+    layers: Vec<Layer>,
+}
+
+impl IgnoreStack {
+    /// a stack seeded with `implicit`, a fixed set of directory names that
+    /// are always ignored regardless of any `.gitignore` (e.g. `target`)
+    pub fn with_implicit(root: &Path, implicit: &[&str]) -> Self {
+        let patterns = implicit
+            .iter()
+            .map(|name| Pattern {
+                glob: name.to_string(),
+                negated: false,
+                dir_only: true,
+                anchored: false,
+            })
+            .collect();
+        Self {
+            layers: vec![Layer {
+                dir: root.to_path_buf(),
+                patterns,
+            }],
+        }
+    }
+
+    /// parse `dir/.gitignore`, if any, and push it as the new nearest layer
+    pub fn push(&mut self, dir: &Path) {
+        let patterns = fs::read_to_string(dir.join(".gitignore"))
+            .map(|content| content.lines().filter_map(parse_line).collect())
+            .unwrap_or_default();
+        self.layers.push(Layer {
+            dir: dir.to_path_buf(),
+            patterns,
+        });
+    }
+
+    /// pop the layer most recently pushed with `push`
+    pub fn pop(&mut self) {
+        self.layers.pop();
+    }
+
+    /// true if `path` (a direct or indirect child of the root this stack was
+    /// created with) should be ignored
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+        for (i, layer) in self.layers.iter().enumerate() {
+            let rel = match path.strip_prefix(&layer.dir) {
+                Ok(rel) => rel,
+                Err(_) => continue,
+            };
+            let rel_str = rel.to_string_lossy();
+            let file_name = path
+                .file_name()
+                .map(|v| v.to_string_lossy())
+                .unwrap_or_default();
+
+            for pattern in &layer.patterns {
+                if pattern.dir_only && !is_dir {
+                    continue;
+                }
+                let text = if pattern.anchored || pattern.glob.contains('/') {
+                    rel_str.as_ref()
+                } else {
+                    file_name.as_ref()
+                };
+                if glob_match(&pattern.glob, text) {
+                    // the implicit layer (always first) cannot be re-included
+                    // by a later `!` pattern
+                    if i == 0 && !pattern.negated {
+                        return true;
+                    }
+                    ignored = !pattern.negated;
+                }
+            }
+        }
+        ignored
+    }
+}
+
+fn parse_line(line: &str) -> Option<Pattern> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (anchored, line) = match line.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+
+    Some(Pattern {
+        glob: line.to_string(),
+        negated,
+        dir_only,
+        anchored,
+    })
+}
+
+/// minimal glob matcher: `*` matches any run of characters, everything else
+/// is matched literally. gitignore's more exotic syntax (`**`, `?`, `[abc]`)
+/// is not supported, and anchoring (a leading `/`, or any `/` elsewhere in
+/// the pattern) is handled by the caller before the text is matched here.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    fn helper(pattern: &[u8], text: &[u8]) -> bool {
+        match pattern.first() {
+            None => text.is_empty(),
+            Some(b'*') => {
+                helper(&pattern[1..], text)
+                    || (!text.is_empty() && helper(pattern, &text[1..]))
+            }
+            Some(c) => matches!(text.first(), Some(t) if t == c) && helper(&pattern[1..], &text[1..]),
+        }
+    }
+    helper(pattern.as_bytes(), text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_match_literal_and_wildcard() {
+        assert!(glob_match("target", "target"));
+        assert!(!glob_match("target", "targets"));
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(!glob_match("*.rs", "main.rs.bak"));
+        assert!(glob_match("scratch*", "scratch_file.rs"));
+    }
+
+    #[test]
+    fn parse_line_handles_negation_and_dir_only() {
+        let p = parse_line("!keep.rs").unwrap();
+        assert!(p.negated);
+        assert!(!p.dir_only);
+
+        let p = parse_line("build/").unwrap();
+        assert!(!p.negated);
+        assert!(p.dir_only);
+
+        assert!(parse_line("# comment").is_none());
+        assert!(parse_line("").is_none());
+    }
+
+    #[test]
+    fn parse_line_strips_leading_slash_and_anchors() {
+        let p = parse_line("/vendor").unwrap();
+        assert_eq!("vendor", p.glob);
+        assert!(p.anchored);
+
+        let p = parse_line("vendor").unwrap();
+        assert!(!p.anchored);
+    }
+
+    #[test]
+    fn is_ignored_anchors_leading_slash_to_its_own_dir() {
+        let mut stack = IgnoreStack::with_implicit(Path::new("/root"), &[]);
+        stack.layers[0].patterns.push(parse_line("/vendor").unwrap());
+
+        assert!(stack.is_ignored(Path::new("/root/vendor"), true));
+        assert!(!stack.is_ignored(Path::new("/root/src/vendor"), true));
+    }
+}
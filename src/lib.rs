@@ -0,0 +1,4 @@
+mod ignore;
+pub mod manifest;
+pub mod names;
+pub mod project;
@@ -1,12 +1,83 @@
+use crate::names::validate_target_name;
 use crate::project::search_manifest;
 use anyhow::{bail, ensure, Context, Result};
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use toml_edit::{value, ArrayOfTables, Document, Item, Table};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use toml_edit::{value, Array, ArrayOfTables, Document, Item, Table};
 
-const KEY_BIN: &str = "bin";
-const KEY_BIN_NAME: &str = "name";
-const KEY_BIN_PATH: &str = "path";
+const KEY_NAME: &str = "name";
+const KEY_PATH: &str = "path";
+const KEY_REQUIRED_FEATURES: &str = "required-features";
+const KEY_EDITION: &str = "edition";
+
+/// Optional fields a user may have hand-authored on a target table, which
+/// `add_target` can also set explicitly via `New`/`Add` flags. Anything left
+/// `None` here is left untouched on an existing target.
+#[derive(Debug, Default, Clone)]
+pub struct TargetFields {
+    pub required_features: Option<Vec<String>>,
+    pub edition: Option<String>,
+}
+
+/// The kind of cargo target this tool manages, mirroring cargo's directory
+/// conventions. See https://doc.rust-lang.org/cargo/reference/cargo-targets.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetKind {
+    Bin,
+    Example,
+    Test,
+    Bench,
+}
+
+impl TargetKind {
+    /// the four kinds `cargo bin` knows how to sync, in scan order
+    pub fn all() -> [TargetKind; 4] {
+        [
+            TargetKind::Bin,
+            TargetKind::Example,
+            TargetKind::Test,
+            TargetKind::Bench,
+        ]
+    }
+
+    /// key of the array-of-tables in Cargo.toml, e.g. `[[bin]]`
+    pub fn key(&self) -> &'static str {
+        match self {
+            TargetKind::Bin => "bin",
+            TargetKind::Example => "example",
+            TargetKind::Test => "test",
+            TargetKind::Bench => "bench",
+        }
+    }
+
+    /// directory cargo expects this kind of target to live under, relative
+    /// to the package root
+    pub fn dir(&self) -> &'static str {
+        match self {
+            TargetKind::Bin => "src/bin",
+            TargetKind::Example => "examples",
+            TargetKind::Test => "tests",
+            TargetKind::Bench => "benches",
+        }
+    }
+}
+
+impl FromStr for TargetKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "bin" => Ok(TargetKind::Bin),
+            "example" => Ok(TargetKind::Example),
+            "test" => Ok(TargetKind::Test),
+            "bench" => Ok(TargetKind::Bench),
+            _ => bail!("unknown target kind {:?}, expect bin/example/test/bench", s),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Manifest {
@@ -21,24 +92,27 @@ impl Manifest {
         Self::open(&path)
     }
 
-    // TODO pub ?
-    fn open(path: &Path) -> Result<Self> {
+    /// Open a specific Cargo.toml, skipping the upward search `new` does.
+    pub fn open(path: &Path) -> Result<Self> {
         let file_content = fs::read_to_string(path)
             .with_context(|| format!("read toml file err, path: {:?}", path))?;
         let mut doc = file_content
             .parse::<Document>()
             .with_context(|| format!("parse toml file err, path: {:?}", path))?;
 
-        // make sure bin is initialized
-        let item = &doc[KEY_BIN];
-        match item {
-            Item::ArrayOfTables(_) => {
-                // already exists bin
-            }
-            Item::None => {
-                doc[KEY_BIN] = Item::ArrayOfTables(ArrayOfTables::default());
+        // make sure every target kind's array-of-tables is initialized
+        for kind in TargetKind::all().iter() {
+            let key = kind.key();
+            let item = &doc[key];
+            match item {
+                Item::ArrayOfTables(_) => {
+                    // already exists
+                }
+                Item::None => {
+                    doc[key] = Item::ArrayOfTables(ArrayOfTables::default());
+                }
+                _ => bail!("{} should be type ArrayOfTables instead of {:?}", key, item),
             }
-            _ => bail!("bin should be type ArrayOfTables instead of {:?}", item),
         }
 
         Ok(Self {
@@ -47,39 +121,38 @@ impl Manifest {
         })
     }
 
-    fn bins(&self) -> &ArrayOfTables {
-        let item = &self.root[KEY_BIN];
+    fn targets(&self, kind: TargetKind) -> &ArrayOfTables {
+        let item = &self.root[kind.key()];
         match item {
             Item::ArrayOfTables(v) => v,
-            _ => panic!("bin should be type ArrayOfTables instead of {:?}", item),
+            _ => panic!("{} should be type ArrayOfTables instead of {:?}", kind.key(), item),
         }
     }
-    fn bins_mut(&mut self) -> &mut ArrayOfTables {
-        let item = &mut self.root[KEY_BIN];
+    fn targets_mut(&mut self, kind: TargetKind) -> &mut ArrayOfTables {
+        let item = &mut self.root[kind.key()];
         match item {
             Item::ArrayOfTables(v) => v,
             _ => panic!("bin should be type ArrayOfTables instead of {:?}", item),
         }
     }
 
-    /// Check if same binary exists.
-    /// exists means name or path is equal to some existed ones.
-    pub fn exists(&self, name: &str, path: &str) -> bool {
-        self.find_bin(name, path).is_some()
+    /// Check if a target of `kind` with the same name or path already exists.
+    pub fn exists(&self, kind: TargetKind, name: &str, path: &str) -> bool {
+        self.find_target(kind, name, path).is_some()
     }
 
-    /// Find a bin's index within ArrayTable, cannot use ArrayTable's iter()
-    /// because there is filter in it.
-    fn find_bin(&self, name: &str, path: &str) -> Option<usize> {
-        let bins = self.bins();
-        for i in 0..bins.len() {
-            if let Some(item) = bins.get(i) {
-                if let Some(v) = item[KEY_BIN_NAME].as_str() {
+    /// Find a target's index within its ArrayOfTables, cannot use
+    /// ArrayOfTables's iter() because there is filter in it.
+    fn find_target(&self, kind: TargetKind, name: &str, path: &str) -> Option<usize> {
+        let targets = self.targets(kind);
+        for i in 0..targets.len() {
+            if let Some(item) = targets.get(i) {
+                if let Some(v) = item[KEY_NAME].as_str() {
                     if v == name {
                         return Some(i);
                     }
                 }
-                if let Some(v) = item[KEY_BIN_PATH].as_str() {
+                if let Some(v) = item[KEY_PATH].as_str() {
                     if v == path {
                         return Some(i);
                     }
@@ -89,42 +162,256 @@ impl Manifest {
         None
     }
 
-    /// Add a bin, only support name and path for now.
-    /// If a bin with same name or path already exists, will remove it first
-    /// then add the new one.
-    ///  About Cargo.toml bin, see cargo book: https://doc.rust-lang.org/cargo/reference/cargo-targets.html#configuring-a-target
-    pub fn add_bin(&mut self, name: &str, path: &str) -> Result<()> {
-        ensure!(!name.is_empty(), "bin.name cannot be empty");
-        ensure!(!path.is_empty(), "bin.path cannot be empty");
+    /// Indices (other than `exclude`) whose name or path matches `name`/`path`.
+    /// Used by `add_target` to find entries that would collide with the
+    /// in-place update at `exclude`.
+    fn find_other_targets(&self, kind: TargetKind, name: &str, path: &str, exclude: usize) -> Vec<usize> {
+        let targets = self.targets(kind);
+        let mut indices = vec![];
+        for i in 0..targets.len() {
+            if i == exclude {
+                continue;
+            }
+            if let Some(item) = targets.get(i) {
+                let name_matches = item[KEY_NAME].as_str() == Some(name);
+                let path_matches = item[KEY_PATH].as_str() == Some(path);
+                if name_matches || path_matches {
+                    indices.push(i);
+                }
+            }
+        }
+        indices
+    }
+
+    /// Add a target, or update it in place if one with the same name or
+    /// path already exists. Updating in place only touches `name`, `path`
+    /// and whatever `fields` sets, so hand-authored keys (`test`, `bench`,
+    /// `doc`, `harness`, `doctest`, ...) on an existing target survive.
+    ///  About Cargo.toml targets, see cargo book: https://doc.rust-lang.org/cargo/reference/cargo-targets.html#configuring-a-target
+    pub fn add_target(
+        &mut self,
+        kind: TargetKind,
+        name: &str,
+        path: &str,
+        fields: &TargetFields,
+    ) -> Result<()> {
+        ensure!(!name.is_empty(), "{}.name cannot be empty", kind.key());
+        ensure!(!path.is_empty(), "{}.path cannot be empty", kind.key());
+        validate_target_name(name)
+            .with_context(|| format!("cannot add {} {:?} ({:?})", kind.key(), name, path))?;
 
-        // remove first
-        self.remove_bin(name, path);
+        let mut index = match self.find_target(kind, name, path) {
+            Some(index) => index,
+            None => {
+                self.targets_mut(kind).append(Table::default());
+                self.targets_mut(kind).len() - 1
+            }
+        };
+
+        // Updating in place only re-targets the entry matched above; if the new
+        // name/path also collides with a *different* entry, drop that other entry
+        // first so we never end up with two tables sharing the same path.
+        let conflicts = self.find_other_targets(kind, name, path, index);
+        for conflict in conflicts.into_iter().rev() {
+            self.targets_mut(kind).remove(conflict);
+            if conflict < index {
+                index -= 1;
+            }
+        }
+
+        let table = self
+            .targets_mut(kind)
+            .get_mut(index)
+            .with_context(|| format!("{} table at index {} missing", kind.key(), index))?;
+        table[KEY_NAME] = value(name);
+        table[KEY_PATH] = value(path);
+        if let Some(required_features) = &fields.required_features {
+            let mut array = Array::default();
+            for feature in required_features {
+                array.push(feature.as_str());
+            }
+            table[KEY_REQUIRED_FEATURES] = value(array);
+        }
+        if let Some(edition) = &fields.edition {
+            table[KEY_EDITION] = value(edition.as_str());
+        }
 
-        // append new bin
-        let mut table = Table::default();
-        table[KEY_BIN_NAME] = value(name);
-        table[KEY_BIN_PATH] = value(path);
-        self.bins_mut().append(table);
+        self.sort_targets(kind);
 
         Ok(())
     }
 
-    /// Remove a bin from manifest. Return true if found and delete.
-    pub fn remove_bin(&mut self, name: &str, path: &str) -> bool {
-        match self.find_bin(name, path) {
+    /// Remove a target from manifest. Return true if found and deleted.
+    pub fn remove_target(&mut self, kind: TargetKind, name: &str, path: &str) -> bool {
+        match self.find_target(kind, name, path) {
             Some(index) => {
-                self.bins_mut().remove(index);
+                self.targets_mut(kind).remove(index);
                 true
             }
             None => false,
         }
     }
 
-    /// Write changes to manifest file
+    /// true if `kind`'s array-of-tables is already ordered by (name, path)
+    pub fn is_sorted(&self, kind: TargetKind) -> bool {
+        self.sort_keys(kind).windows(2).all(|w| w[0] <= w[1])
+    }
+
+    /// Reorder `kind`'s array-of-tables by (name, path) so repeated
+    /// `add`/`tidy` runs produce the same Cargo.toml byte-for-byte, keeping
+    /// diffs minimal and stable.
+    pub fn sort_targets(&mut self, kind: TargetKind) {
+        let mut tables: Vec<Table> = self.targets(kind).iter().cloned().collect();
+        tables.sort_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+
+        let mut sorted = ArrayOfTables::default();
+        for table in tables {
+            sorted.append(table);
+        }
+        self.root[kind.key()] = Item::ArrayOfTables(sorted);
+    }
+
+    fn sort_keys(&self, kind: TargetKind) -> Vec<(String, String)> {
+        self.targets(kind).iter().map(sort_key).collect()
+    }
+
+    /// Iterate over every target of `kind`, calling `f` with its name and path.
+    pub fn foreach_target<F>(&self, kind: TargetKind, mut f: F)
+    where
+        F: FnMut(Option<&str>, Option<&str>),
+    {
+        for table in self.targets(kind).iter() {
+            f(table[KEY_NAME].as_str(), table[KEY_PATH].as_str());
+        }
+    }
+
+    /// Write changes to manifest file. Goes through a temp file + rename so
+    /// a crash or interrupt mid-write never leaves Cargo.toml truncated.
     pub fn write(&self) -> Result<()> {
-        fs::write(&self.path, self.root.to_string_in_original_order())?;
+        write_atomic(&self.path, self.root.to_string_in_original_order().as_bytes())
+    }
+
+    /// the path this manifest was opened from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// the crate name from `[package] name = "..."`, if this manifest has one
+    pub fn package_name(&self) -> Option<&str> {
+        self.root["package"]["name"].as_str()
+    }
+
+    /// true if this manifest declares a `[workspace]`
+    pub fn is_workspace(&self) -> bool {
+        matches!(self.root["workspace"], Item::Table(_))
+    }
+
+    /// true if this manifest declares a `[package]`
+    pub fn is_package(&self) -> bool {
+        matches!(self.root["package"], Item::Table(_))
+    }
+
+    /// resolve `[workspace] members` to the absolute path of each member's
+    /// Cargo.toml, plus this manifest's own path if it is itself a package
+    /// (the common root-crate-plus-workspace layout). Supports the common
+    /// `dir/*` glob shorthand in addition to literal member directories;
+    /// anything more exotic in cargo's member glob syntax is not handled.
+    pub fn workspace_members(&self) -> Result<Vec<PathBuf>> {
+        let root_dir = self
+            .path
+            .parent()
+            .with_context(|| format!("{:?} has no parent dir", self.path))?;
+
+        let members: Vec<String> = self.root["workspace"]["members"]
+            .as_array()
+            .map(|array| {
+                array
+                    .iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut paths = vec![];
+        if self.is_package() {
+            paths.push(
+                fs::canonicalize(&self.path)
+                    .with_context(|| format!("{:?} convert to absolute path err", self.path))?,
+            );
+        }
+        for member in members {
+            if let Some(prefix) = member.strip_suffix("/*") {
+                let base = root_dir.join(prefix);
+                if !base.is_dir() {
+                    continue;
+                }
+                for entry in
+                    fs::read_dir(&base).with_context(|| format!("read_dir {:?} err", base))?
+                {
+                    let entry = entry.with_context(|| "dir entry err")?;
+                    let candidate = entry.path().join("Cargo.toml");
+                    if candidate.exists() {
+                        paths.push(fs::canonicalize(&candidate)
+                            .with_context(|| format!("{:?} convert to absolute path err", candidate))?);
+                    }
+                }
+            } else {
+                let candidate = root_dir.join(&member).join("Cargo.toml");
+                ensure!(candidate.exists(), "workspace member {:?} has no Cargo.toml", member);
+                paths.push(
+                    fs::canonicalize(&candidate)
+                        .with_context(|| format!("{:?} convert to absolute path err", candidate))?,
+                );
+            }
+        }
+        Ok(paths)
+    }
+}
+
+/// write `contents` to `path` atomically: serialize to a uniquely named temp
+/// file next to `path` (same directory, so the rename below stays on one
+/// filesystem), flush and sync it, then rename it over `path`. `fs::rename`
+/// replaces an existing destination on both Unix and Windows, so readers
+/// only ever see the old or the fully-written new content, never a partial
+/// file. The temp file is removed if anything goes wrong.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let dir = path
+        .parent()
+        .with_context(|| format!("{:?} has no parent dir", path))?;
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("{:?} has no file name", path))?
+        .to_string_lossy();
+    let suffix = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let tmp_path = dir.join(format!(".{}.{}.{}.tmp", file_name, std::process::id(), suffix));
+
+    let result = (|| -> Result<()> {
+        let mut file = fs::File::create(&tmp_path)
+            .with_context(|| format!("create temp file {:?} err", tmp_path))?;
+        file.write_all(contents)
+            .with_context(|| format!("write temp file {:?} err", tmp_path))?;
+        file.sync_all()
+            .with_context(|| format!("sync temp file {:?} err", tmp_path))?;
+        fs::rename(&tmp_path, path)
+            .with_context(|| format!("rename {:?} to {:?} err", tmp_path, path))?;
         Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
     }
+    result
+}
+
+/// the (name, path) pair a target table is ordered by
+fn sort_key(table: &Table) -> (String, String) {
+    (
+        table[KEY_NAME].as_str().unwrap_or("").to_string(),
+        table[KEY_PATH].as_str().unwrap_or("").to_string(),
+    )
 }
 
 impl ToString for Manifest {
@@ -141,7 +428,9 @@ mod tests {
 
     fn new_empty_manifest() -> Manifest {
         let mut root = Document::new();
-        root[KEY_BIN] = Item::ArrayOfTables(ArrayOfTables::default());
+        for kind in TargetKind::all().iter() {
+            root[kind.key()] = Item::ArrayOfTables(ArrayOfTables::default());
+        }
         Manifest {
             root,
             path: PathBuf::new(),
@@ -156,7 +445,7 @@ mod tests {
         let file_path = search_manifest_from(&dir, "test-cargo.toml")?;
         let manifest = Manifest::open(&file_path)?;
 
-        assert!(matches!(manifest.root[KEY_BIN], Item::ArrayOfTables(_)));
+        assert!(matches!(manifest.root[TargetKind::Bin.key()], Item::ArrayOfTables(_)));
 
         Ok(())
     }
@@ -164,34 +453,115 @@ mod tests {
     #[test]
     fn add_bin() -> Result<()> {
         let mut manifest = new_empty_manifest();
-        manifest.add_bin("bin1", "src/b1.rs")?;
-        manifest.add_bin("bin2", "src/b2.rs")?;
-        manifest.add_bin("bin3", "src/b3.rs")?;
-        manifest.add_bin("bin1", "src/2/b1.rs")?;
+        manifest.add_target(TargetKind::Bin, "bin1", "src/b1.rs", &TargetFields::default())?;
+        manifest.add_target(TargetKind::Bin, "bin2", "src/b2.rs", &TargetFields::default())?;
+        manifest.add_target(TargetKind::Bin, "bin3", "src/b3.rs", &TargetFields::default())?;
+        // re-adding bin1 under a new path updates it in place rather than
+        // moving it to the end of the array
+        manifest.add_target(TargetKind::Bin, "bin1", "src/2/b1.rs", &TargetFields::default())?;
 
         let expected = r#"[[bin]]
+name = "bin1"
+path = "src/2/b1.rs"
+[[bin]]
 name = "bin2"
 path = "src/b2.rs"
 [[bin]]
 name = "bin3"
 path = "src/b3.rs"
-[[bin]]
-name = "bin1"
-path = "src/2/b1.rs"
 "#;
         assert_eq!(expected, manifest.to_string());
 
         Ok(())
     }
 
+    #[test]
+    fn add_bin_preserves_other_fields() -> Result<()> {
+        let mut manifest = new_empty_manifest();
+        manifest.add_target(TargetKind::Bin, "bin1", "src/b1.rs", &TargetFields::default())?;
+        manifest.targets_mut(TargetKind::Bin).get_mut(0).unwrap()["doctest"] = value(false);
+
+        // updating the path should leave the hand-authored `doctest` key alone
+        manifest.add_target(TargetKind::Bin, "bin1", "src/bin1.rs", &TargetFields::default())?;
+        assert_eq!(
+            Some(false),
+            manifest.targets(TargetKind::Bin).get(0).unwrap()["doctest"].as_bool()
+        );
+        assert_eq!(
+            Some("src/bin1.rs"),
+            manifest.targets(TargetKind::Bin).get(0).unwrap()[KEY_PATH].as_str()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_bin_sets_optional_fields() -> Result<()> {
+        let mut manifest = new_empty_manifest();
+        let fields = TargetFields {
+            required_features: Some(vec!["foo".to_string(), "bar".to_string()]),
+            edition: Some("2021".to_string()),
+        };
+        manifest.add_target(TargetKind::Bin, "bin1", "src/b1.rs", &fields)?;
+
+        let table = manifest.targets(TargetKind::Bin).get(0).unwrap();
+        assert_eq!(Some("2021"), table[KEY_EDITION].as_str());
+        assert_eq!(
+            vec!["foo", "bar"],
+            table[KEY_REQUIRED_FEATURES]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|v| v.as_str().unwrap())
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_bin_drops_other_entry_that_now_collides() -> Result<()> {
+        let mut manifest = new_empty_manifest();
+        // name "b" matches the first entry, but the new path "src/bin/b.rs"
+        // already belongs to the second entry
+        manifest.add_target(TargetKind::Bin, "b", "src/bin/other.rs", &TargetFields::default())?;
+        manifest.add_target(TargetKind::Bin, "y", "src/bin/b.rs", &TargetFields::default())?;
+
+        manifest.add_target(TargetKind::Bin, "b", "src/bin/b.rs", &TargetFields::default())?;
+
+        assert_eq!(1, manifest.targets(TargetKind::Bin).len());
+        let table = manifest.targets(TargetKind::Bin).get(0).unwrap();
+        assert_eq!(Some("b"), table[KEY_NAME].as_str());
+        assert_eq!(Some("src/bin/b.rs"), table[KEY_PATH].as_str());
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_target_keeps_array_sorted() -> Result<()> {
+        let mut manifest = new_empty_manifest();
+        manifest.add_target(TargetKind::Bin, "zeta", "src/bin/zeta.rs", &TargetFields::default())?;
+        manifest.add_target(TargetKind::Bin, "alpha", "src/bin/alpha.rs", &TargetFields::default())?;
+
+        assert!(manifest.is_sorted(TargetKind::Bin));
+        let names: Vec<&str> = manifest
+            .targets(TargetKind::Bin)
+            .iter()
+            .map(|t| t[KEY_NAME].as_str().unwrap())
+            .collect();
+        assert_eq!(vec!["alpha", "zeta"], names);
+
+        Ok(())
+    }
+
     #[test]
     fn get_bins() -> Result<()> {
         let mut manifest = new_empty_manifest();
-        assert_eq!(0, manifest.bins().len());
-        manifest.add_bin("bin1", "src/b1.rs")?;
-        assert_eq!(1, manifest.bins().len());
-        manifest.add_bin("bin2", "src/b2.rs")?;
-        assert_eq!(2, manifest.bins().len());
+        assert_eq!(0, manifest.targets(TargetKind::Bin).len());
+        manifest.add_target(TargetKind::Bin, "bin1", "src/b1.rs", &TargetFields::default())?;
+        assert_eq!(1, manifest.targets(TargetKind::Bin).len());
+        manifest.add_target(TargetKind::Bin, "bin2", "src/b2.rs", &TargetFields::default())?;
+        assert_eq!(2, manifest.targets(TargetKind::Bin).len());
 
         Ok(())
     }
@@ -199,22 +569,44 @@ path = "src/2/b1.rs"
     #[test]
     fn bin_exists() -> Result<()> {
         let mut manifest = new_empty_manifest();
-        assert!(!manifest.exists("bin1", "src/b1.rs"));
+        assert!(!manifest.exists(TargetKind::Bin, "bin1", "src/b1.rs"));
 
-        manifest.add_bin("bin1", "src/b1.rs")?;
-        assert!(manifest.exists("bin1", ""));
-        assert!(manifest.exists("", "src/b1.rs"));
+        manifest.add_target(TargetKind::Bin, "bin1", "src/b1.rs", &TargetFields::default())?;
+        assert!(manifest.exists(TargetKind::Bin, "bin1", ""));
+        assert!(manifest.exists(TargetKind::Bin, "", "src/b1.rs"));
         Ok(())
     }
 
     #[test]
     fn find_bin() {
         let mut manifest = new_empty_manifest();
-        let index = manifest.find_bin("bin1", "src/b1.rs");
+        let index = manifest.find_target(TargetKind::Bin, "bin1", "src/b1.rs");
         assert!(index.is_none());
 
-        manifest.add_bin("bin1", "src/b1.rs").unwrap();
-        assert_eq!(manifest.find_bin("bin1", "").unwrap(), 0);
-        assert_eq!(manifest.find_bin("", "src/b1.rs").unwrap(), 0);
+        manifest
+            .add_target(TargetKind::Bin, "bin1", "src/b1.rs", &TargetFields::default())
+            .unwrap();
+        assert_eq!(
+            manifest.find_target(TargetKind::Bin, "bin1", "").unwrap(),
+            0
+        );
+        assert_eq!(
+            manifest.find_target(TargetKind::Bin, "", "src/b1.rs").unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn targets_are_independent_per_kind() -> Result<()> {
+        let mut manifest = new_empty_manifest();
+        manifest.add_target(TargetKind::Bin, "same", "src/bin/same.rs", &TargetFields::default())?;
+        manifest.add_target(TargetKind::Example, "same", "examples/same.rs", &TargetFields::default())?;
+
+        assert_eq!(1, manifest.targets(TargetKind::Bin).len());
+        assert_eq!(1, manifest.targets(TargetKind::Example).len());
+        assert!(manifest.exists(TargetKind::Bin, "same", "src/bin/same.rs"));
+        assert!(manifest.exists(TargetKind::Example, "same", "examples/same.rs"));
+
+        Ok(())
     }
 }
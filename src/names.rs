@@ -0,0 +1,91 @@
+use anyhow::{bail, Result};
+
+/// names cargo refuses to build as a target, on top of Rust keywords and
+/// invalid identifier characters. Lifted from cargo's own target name
+/// restrictions (see cargo's `restricted_names` module).
+const RESTRICTED_NAMES: &[&str] = &["test", "deps", "examples", "build", "incremental"];
+
+/// reserved device names on Windows; cargo rejects these as target names
+/// even on other platforms, since the crate may eventually build there.
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Rust keywords (strict and reserved), which cannot be used as a crate or
+/// target name.
+const KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+    "where", "while", "async", "await", "dyn", "abstract", "become", "box", "do", "final",
+    "macro", "override", "priv", "typeof", "unsized", "virtual", "yield", "try",
+];
+
+/// Validate that `name` is usable as a `[[bin]]`/`[[example]]`/`[[test]]`/`[[bench]]`
+/// target name, erroring with the reason cargo would reject it otherwise.
+pub fn validate_target_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("target name cannot be empty");
+    }
+
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!(
+            "invalid target name {:?}: names may only contain letters, digits, \"-\" and \"_\"",
+            name
+        );
+    }
+
+    let lower = name.to_ascii_lowercase();
+    if KEYWORDS.contains(&name) {
+        bail!("invalid target name {:?}: it is a Rust keyword", name);
+    }
+    if RESTRICTED_NAMES.contains(&lower.as_str()) {
+        bail!(
+            "invalid target name {:?}: cargo reserves this name for its own use",
+            name
+        );
+    }
+    if WINDOWS_RESERVED_NAMES.contains(&lower.as_str()) {
+        bail!(
+            "invalid target name {:?}: it is a reserved Windows device name",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_normal_names() {
+        assert!(validate_target_name("hello-world").is_ok());
+        assert!(validate_target_name("my_tool").is_ok());
+    }
+
+    #[test]
+    fn rejects_empty_and_invalid_chars() {
+        assert!(validate_target_name("").is_err());
+        assert!(validate_target_name("foo/bar").is_err());
+        assert!(validate_target_name("foo bar").is_err());
+    }
+
+    #[test]
+    fn rejects_keywords_and_restricted_names() {
+        assert!(validate_target_name("fn").is_err());
+        assert!(validate_target_name("test").is_err());
+        assert!(validate_target_name("build").is_err());
+    }
+
+    #[test]
+    fn rejects_windows_reserved_names_case_insensitively() {
+        assert!(validate_target_name("CON").is_err());
+        assert!(validate_target_name("com1").is_err());
+    }
+}
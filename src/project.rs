@@ -1,11 +1,15 @@
+use crate::ignore::IgnoreStack;
+use crate::manifest::TargetKind;
 use anyhow::{Context, Result};
-use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 use syn::Item;
 
+/// folders that are always skipped, regardless of `.gitignore`
+const IMPLICIT_IGNORES: [&str; 3] = ["target", ".git", ".github"];
+
 const CARGO_TOML: &str = "Cargo.toml";
 
 /// return cargo project root path (absolute path)
@@ -41,27 +45,48 @@ pub fn search_manifest_from(start_dir: &PathBuf, file_name: &str) -> Result<Path
     }
 }
 
-/// find rust source file with main() from the specified dir
-/// TODO for now ignore some folders like target, .git, .github
-pub fn find_main_file(dir: &Path) -> Result<Vec<PathBuf>> {
-    let mut ignored_folders = HashSet::new();
-    for folder in ["target", "src/bin", ".git", ".github"].iter() {
-        ignored_folders.insert(dir.join(*folder));
-    }
-    let mut files = vec![];
+/// a source file discovered under the project root, together with the
+/// target kind it should be registered under
+#[derive(Debug, Clone)]
+pub struct MainFile {
+    pub path: PathBuf,
+    pub kind: TargetKind,
+}
 
-    fn find(dir: &Path, files: &mut Vec<PathBuf>, ignored: &HashSet<PathBuf>) -> Result<()> {
+/// find rust source files to sync into Cargo.toml, following cargo's own
+/// directory conventions: files under `examples/` become `[[example]]`,
+/// `tests/` become `[[test]]`, `benches/` become `[[bench]]`, and everything
+/// else with a `fn main()` stays `[[bin]]` (this covers both `src/main.rs`
+/// and `src/bin/*.rs`).
+/// respects `.gitignore` files found while walking (nested `.gitignore`s
+/// included), plus a fixed implicit skip list for directories no project
+/// should ever want scanned (see `IMPLICIT_IGNORES`).
+pub fn find_main_file(dir: &Path) -> Result<Vec<MainFile>> {
+    let mut files = vec![];
+    let mut ignores = IgnoreStack::with_implicit(dir, &IMPLICIT_IGNORES);
+
+    fn find(
+        root: &Path,
+        dir: &Path,
+        files: &mut Vec<MainFile>,
+        ignores: &mut IgnoreStack,
+    ) -> Result<()> {
         if !dir.is_dir() {
             return Ok(());
         }
-        if ignored.contains(&dir.to_path_buf()) {
-            return Ok(());
-        }
+
+        ignores.push(dir);
         for entry in fs::read_dir(dir).with_context(|| format!("read_dir err, dir: {:?}", dir))? {
             let entry = entry.with_context(|| "dir entry err")?;
             let path = entry.path();
-            if path.is_dir() {
-                find(&path, files, ignored)?;
+            let is_dir = path.is_dir();
+            if ignores.is_ignored(&path, is_dir) {
+                // a directory match prunes the whole subtree
+                continue;
+            }
+
+            if is_dir {
+                find(root, &path, files, ignores)?;
                 continue;
             }
 
@@ -73,18 +98,66 @@ pub fn find_main_file(dir: &Path) -> Result<Vec<PathBuf>> {
                 continue;
             }
 
-            if contains_main(&path)? {
-                files.push(path)
+            let kind = target_kind_of(root, &path);
+            // tests, benches and examples follow cargo's own auto-discovery:
+            // only files directly in the kind's dir, or `<subdir>/main.rs`
+            // one level below it, count (e.g. a `tests/common/mod.rs` helper
+            // does not become its own integration-test crate). tests and
+            // benches are driven by harness attributes, not `fn main()`, so
+            // every auto-discovered file there is a target; bins and
+            // examples additionally need to actually define one.
+            let is_target = match kind {
+                TargetKind::Bin => contains_main(&path)?,
+                TargetKind::Test | TargetKind::Bench => is_auto_discovered(root, &path, kind),
+                TargetKind::Example => is_auto_discovered(root, &path, kind) && contains_main(&path)?,
+            };
+            if is_target {
+                files.push(MainFile { path, kind });
             }
         }
+        ignores.pop();
         Ok(())
     }
 
-    find(dir, &mut files, &ignored_folders)?;
+    find(dir, dir, &mut files, &mut ignores)?;
 
     Ok(files)
 }
 
+/// classify a discovered `.rs` file by which directory convention it lives
+/// under, relative to the project root. anything outside `examples/`,
+/// `tests/`, `benches/` is assumed to be a `[[bin]]` candidate.
+fn target_kind_of(root: &Path, path: &Path) -> TargetKind {
+    let top = path
+        .strip_prefix(root)
+        .ok()
+        .and_then(|rel| rel.components().next())
+        .and_then(|c| c.as_os_str().to_str());
+
+    match top {
+        Some("examples") => TargetKind::Example,
+        Some("tests") => TargetKind::Test,
+        Some("benches") => TargetKind::Bench,
+        _ => TargetKind::Bin,
+    }
+}
+
+/// true if `path` (already classified as `kind`) sits where cargo's own
+/// auto-discovery would find it: directly in `kind.dir()`, or in
+/// `<subdir>/main.rs` one level below it. Anything deeper (e.g.
+/// `tests/common/mod.rs`) is a helper module, not a target of its own.
+fn is_auto_discovered(root: &Path, path: &Path, kind: TargetKind) -> bool {
+    let rel = match path.strip_prefix(root.join(kind.dir())) {
+        Ok(rel) => rel,
+        Err(_) => return false,
+    };
+    match rel.components().count() {
+        1 => true,
+        2 => rel.file_name().and_then(|f| f.to_str()) == Some("main.rs"),
+        _ => false,
+    }
+}
+
 // parse file and see if the file contains fn main()
 fn contains_main(path: &Path) -> Result<bool> {
     let mut file = fs::File::open(path).with_context(|| format!("open file {:?} err", path))?;
@@ -112,4 +185,25 @@ mod tests {
         let file_path = search_manifest_from(&dir, "test-cargo.toml").expect("search should be ok");
         println!("file_path: {:?}", file_path);
     }
+
+    #[test]
+    fn is_auto_discovered_ignores_nested_helper_modules() {
+        let root = Path::new("/proj");
+        assert!(is_auto_discovered(root, Path::new("/proj/tests/smoke.rs"), TargetKind::Test));
+        assert!(is_auto_discovered(
+            root,
+            Path::new("/proj/tests/cli/main.rs"),
+            TargetKind::Test
+        ));
+        assert!(!is_auto_discovered(
+            root,
+            Path::new("/proj/tests/common/mod.rs"),
+            TargetKind::Test
+        ));
+        assert!(!is_auto_discovered(
+            root,
+            Path::new("/proj/tests/cli/helpers/util.rs"),
+            TargetKind::Test
+        ));
+    }
 }